@@ -1,9 +1,13 @@
-use libc::{c_uint, size_t, mode_t};
+use libc::{c_int, c_uint, c_void, size_t, mode_t};
 use std::io::FilePermission;
+use std::mem;
 use std::ptr;
 use std::sync::Mutex;
 
-use error::{LmdbResult, lmdb_result};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+
+use error::{LmdbError, LmdbResult, lmdb_result};
 use database::Database;
 use ffi::*;
 use transaction::{RoTransaction, RwTransaction, Transaction, TransactionExt};
@@ -14,6 +18,8 @@ use transaction::{RoTransaction, RwTransaction, Transaction, TransactionExt};
 pub struct Environment {
     env: *mut MDB_env,
     dbi_open_mutex: Mutex<()>,
+    borrowed: bool,
+    autogrow_step: Option<size_t>,
 }
 
 impl Environment {
@@ -24,10 +30,52 @@ impl Environment {
             flags: EnvironmentFlags::empty(),
             max_readers: None,
             max_dbs: None,
-            map_size: None
+            map_size: None,
+            encryption_key: None,
+            autogrow_step: None,
         }
     }
 
+    /// Adopts ownership of an already-opened `MDB_env` handle, such as one created by another
+    /// language binding or handed off by a parent process.
+    ///
+    /// The returned `Environment` closes `env` via `mdb_env_close` when it is dropped, exactly as
+    /// one opened through `EnvironmentBuilder::open` would. Use `borrow_raw` instead if ownership
+    /// of `env` should remain elsewhere.
+    ///
+    /// ## Unsafety
+    ///
+    /// `env` must be a valid, open `MDB_env` handle, and the caller must not use it, or any other
+    /// `Environment` wrapping it, after the returned value is dropped.
+    pub unsafe fn from_raw(env: *mut MDB_env) -> Environment {
+        Environment { env: env, dbi_open_mutex: Mutex::new(()), borrowed: false, autogrow_step: None }
+    }
+
+    /// Wraps an already-opened `MDB_env` handle without taking ownership of it.
+    ///
+    /// Unlike `from_raw`, dropping the returned `Environment` does not call `mdb_env_close`; this
+    /// lets multiple `Environment` wrappers coexist over the same underlying handle while a single
+    /// owner (elsewhere) is responsible for eventually closing it.
+    ///
+    /// ## Unsafety
+    ///
+    /// `env` must be a valid, open `MDB_env` handle for as long as the returned value, and any
+    /// other wrapper borrowing it, are in use.
+    pub unsafe fn borrow_raw(env: *mut MDB_env) -> Environment {
+        Environment { env: env, dbi_open_mutex: Mutex::new(()), borrowed: true, autogrow_step: None }
+    }
+
+    /// Consumes the environment and returns the raw `MDB_env` handle without closing it.
+    ///
+    /// This relinquishes this wrapper's ownership (if any) of the handle without running `Drop`,
+    /// so the caller becomes responsible for eventually calling `mdb_env_close`, or for handing
+    /// the handle to another owner.
+    pub fn into_raw(self) -> *mut MDB_env {
+        let env = self.env;
+        mem::forget(self);
+        env
+    }
+
     /// Returns a raw pointer to the underlying LMDB environment.
     ///
     /// The caller **must** ensure that the pointer is not dereferenced after the lifetime of the
@@ -86,6 +134,52 @@ impl Environment {
         Ok(DatabaseFlags::from_bits(flags).unwrap())
     }
 
+    /// Returns statistics about this environment.
+    pub fn stat(&self) -> LmdbResult<Stat> {
+        let mut stat = MDB_stat { ms_psize: 0,
+                                   ms_depth: 0,
+                                   ms_branch_pages: 0,
+                                   ms_leaf_pages: 0,
+                                   ms_overflow_pages: 0,
+                                   ms_entries: 0 };
+        unsafe {
+            try!(lmdb_result(mdb_env_stat(self.env, &mut stat)));
+        }
+        Ok(Stat::from_raw(&stat))
+    }
+
+    /// Returns information about this environment, such as the current map size and the number
+    /// of readers currently attached.
+    pub fn info(&self) -> LmdbResult<Info> {
+        let mut info = MDB_envinfo { me_mapaddr: ptr::null(),
+                                      me_mapsize: 0,
+                                      me_last_pgno: 0,
+                                      me_last_txnid: 0,
+                                      me_maxreaders: 0,
+                                      me_numreaders: 0 };
+        unsafe {
+            try!(lmdb_result(mdb_env_info(self.env, &mut info)));
+        }
+        Ok(Info::from_raw(&info))
+    }
+
+    /// Returns statistics about a single database, scoped to an already-open transaction.
+    ///
+    /// Use this instead of `stat`, which reports on the environment's main (unnamed) database, to
+    /// measure one named database.
+    pub fn stat_db<'env, T: Transaction>(&self, txn: &T, db: Database<'env>) -> LmdbResult<Stat> {
+        let mut stat = MDB_stat { ms_psize: 0,
+                                   ms_depth: 0,
+                                   ms_branch_pages: 0,
+                                   ms_leaf_pages: 0,
+                                   ms_overflow_pages: 0,
+                                   ms_entries: 0 };
+        unsafe {
+            try!(lmdb_result(mdb_stat(txn.txn(), db.dbi(), &mut stat)));
+        }
+        Ok(Stat::from_raw(&stat))
+    }
+
     /// Create a read-only transaction for use with the environment.
     pub fn begin_read_txn<'env>(&'env self) -> LmdbResult<RoTransaction<'env>> {
         RoTransaction::new(self)
@@ -122,25 +216,239 @@ impl Environment {
     pub unsafe fn close_db(&self, db: Database) {
         mdb_dbi_close(self.env, db.dbi())
     }
+
+    /// Grows or shrinks the environment's memory map at runtime.
+    ///
+    /// LMDB only permits this while no transaction is open anywhere in the current process.
+    /// Taking `&mut self` makes the borrow checker reject any outstanding `RoTransaction` or
+    /// `RwTransaction` borrowed from *this* `Environment` value, but that is only half the
+    /// invariant: LMDB's restriction is process-wide, not per-wrapper. `Environment::borrow_raw`
+    /// lets multiple `Environment` values alias the same underlying `MDB_env`, and a transaction
+    /// open through one of those aliases is invisible to another alias's borrow checker. Callers
+    /// who mix `borrow_raw` with `set_map_size_runtime` are responsible for ensuring no alias has
+    /// an active transaction; this method cannot detect or prevent that case.
+    pub fn set_map_size_runtime(&mut self, new_size: size_t) -> LmdbResult<()> {
+        unsafe {
+            lmdb_result(mdb_env_set_mapsize(self.env, new_size))
+        }
+    }
+
+    /// Runs `f` inside a read-write transaction and commits it.
+    ///
+    /// If the environment was configured with `EnvironmentBuilder::set_autogrow` and either `f`
+    /// or the commit fails with `MDB_MAP_FULL`, the map is grown by the configured step and the
+    /// whole operation -- beginning a fresh transaction, running `f` again, and committing -- is
+    /// retried exactly once before giving up.
+    pub fn with_write_txn<F>(&mut self, mut f: F) -> LmdbResult<()>
+        where F: FnMut(&mut RwTransaction) -> LmdbResult<()>
+    {
+        match self.try_write_txn(&mut f) {
+            Err(LmdbError::MapFull) => {
+                match self.autogrow_step {
+                    Some(step) => {
+                        let info = try!(self.info());
+                        try!(self.set_map_size_runtime(info.map_size() + step));
+                        self.try_write_txn(&mut f)
+                    }
+                    None => Err(LmdbError::MapFull),
+                }
+            }
+            result => result,
+        }
+    }
+
+    fn try_write_txn<F>(&self, f: &mut F) -> LmdbResult<()>
+        where F: FnMut(&mut RwTransaction) -> LmdbResult<()>
+    {
+        let mut txn = try!(self.begin_write_txn());
+        try!(f(&mut txn));
+        txn.commit()
+    }
+
+    /// Creates a consistent, point-in-time copy of this environment at `path`.
+    ///
+    /// The copy proceeds while other transactions are active; it never blocks writers and is
+    /// always internally consistent. `path` must refer to a directory that already exists and is
+    /// empty. This is equivalent to `copy_with_options(path, false)`.
+    pub fn copy(&self, path: &Path) -> LmdbResult<()> {
+        self.copy_with_options(path, false)
+    }
+
+    /// Creates a copy of this environment at `path`, optionally compacting it.
+    ///
+    /// When `compact` is `true`, free and unused pages are omitted from the copy, which shrinks
+    /// the output at the cost of a somewhat slower copy. When `false`, the copy is made page for
+    /// page, which is faster but preserves the environment's current size on disk.
+    pub fn copy_with_options(&self, path: &Path, compact: bool) -> LmdbResult<()> {
+        let flags = if compact { MDB_CP_COMPACT } else { 0 };
+        unsafe {
+            lmdb_result(mdb_env_copy2(self.env, path.to_c_str().as_ptr(), flags))
+        }
+    }
+
+    /// Streams a copy of this environment to an already-open file descriptor, such as a pipe or
+    /// socket, rather than writing to a path on disk.
+    ///
+    /// See `copy_with_options` for the meaning of `compact`.
+    pub fn copy_to_fd(&self, fd: c_int, compact: bool) -> LmdbResult<()> {
+        let flags = if compact { MDB_CP_COMPACT } else { 0 };
+        unsafe {
+            lmdb_result(mdb_env_copyfd2(self.env, fd, flags))
+        }
+    }
+
+    /// Checks for stale entries in the reader lock table.
+    ///
+    /// Readers tied to a process or thread that has since died are cleared from the table, which
+    /// allows the free list to reclaim pages those readers were holding back. Returns the number
+    /// of stale readers that were cleared. This should be run periodically by long-lived
+    /// processes, since a crashed reader otherwise pins old pages for the life of the environment.
+    pub fn reader_check(&self) -> LmdbResult<u32> {
+        let mut dead: c_int = 0;
+        unsafe {
+            try!(lmdb_result(mdb_reader_check(self.env, &mut dead)));
+        }
+        Ok(dead as u32)
+    }
+
+    /// Lists the readers currently tracked in the reader lock table.
+    ///
+    /// Use this alongside `reader_check` to see which readers are holding back the free list
+    /// before deciding whether they're stuck (e.g. a long-running analytics transaction) or simply
+    /// dead and waiting to be cleared.
+    pub fn reader_list(&self) -> LmdbResult<Vec<ReaderInfo>> {
+        let mut readers: Vec<ReaderInfo> = Vec::new();
+        unsafe {
+            try!(lmdb_result(mdb_reader_list(self.env,
+                                              reader_list_callback,
+                                              &mut readers as *mut _ as *mut c_void)));
+        }
+        Ok(readers)
+    }
+}
+
+/// A single entry from the reader lock table, as reported by `Environment::reader_list`.
+#[deriving(Show, PartialEq, Eq, Clone)]
+pub struct ReaderInfo {
+    /// The raw, formatted line LMDB produced for this reader (process id, thread id, transaction
+    /// id, as rendered by `mdb_reader_list`).
+    pub line: String,
+}
+
+/// `mdb_reader_list` callback: appends each formatted line it's handed to the `Vec<ReaderInfo>`
+/// pointed to by `ctx`.
+extern "C" fn reader_list_callback(msg: *const ::libc::c_char, ctx: *mut c_void) -> c_int {
+    unsafe {
+        let readers = &mut *(ctx as *mut Vec<ReaderInfo>);
+        let line = String::from_utf8_lossy(::std::ffi::c_str_to_bytes(&msg)).into_owned();
+        readers.push(ReaderInfo { line: line });
+    }
+    0
 }
 
 impl Drop for Environment {
     fn drop(&mut self) {
-        unsafe { mdb_env_close(self.env) }
+        if !self.borrowed {
+            unsafe { mdb_env_close(self.env) }
+        }
     }
 }
 
+///////////////////////////////////////////////////////////////////////////////////////////////////
+//// Stat / Info
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Statistics for a database in the environment.
+#[deriving(Show, PartialEq, Eq, Copy, Clone)]
+pub struct Stat {
+    psize: c_uint,
+    depth: c_uint,
+    branch_pages: size_t,
+    leaf_pages: size_t,
+    overflow_pages: size_t,
+    entries: size_t,
+}
+
+impl Stat {
+
+    fn from_raw(stat: &MDB_stat) -> Stat {
+        Stat { psize: stat.ms_psize,
+               depth: stat.ms_depth,
+               branch_pages: stat.ms_branch_pages,
+               leaf_pages: stat.ms_leaf_pages,
+               overflow_pages: stat.ms_overflow_pages,
+               entries: stat.ms_entries }
+    }
+
+    /// Size of a database page, in bytes.
+    pub fn page_size(&self) -> c_uint { self.psize }
+
+    /// Depth (height) of the B-tree.
+    pub fn depth(&self) -> c_uint { self.depth }
+
+    /// Number of internal (non-leaf) pages.
+    pub fn branch_pages(&self) -> size_t { self.branch_pages }
+
+    /// Number of leaf pages.
+    pub fn leaf_pages(&self) -> size_t { self.leaf_pages }
+
+    /// Number of overflow pages.
+    pub fn overflow_pages(&self) -> size_t { self.overflow_pages }
+
+    /// Number of data items.
+    pub fn entries(&self) -> size_t { self.entries }
+}
+
+/// Information about the environment as a whole.
+#[deriving(Show, PartialEq, Eq, Copy, Clone)]
+pub struct Info {
+    map_size: size_t,
+    last_pgno: size_t,
+    last_txnid: size_t,
+    max_readers: c_uint,
+    num_readers: c_uint,
+}
+
+impl Info {
+
+    fn from_raw(info: &MDB_envinfo) -> Info {
+        Info { map_size: info.me_mapsize,
+               last_pgno: info.me_last_pgno,
+               last_txnid: info.me_last_txnid,
+               max_readers: info.me_maxreaders,
+               num_readers: info.me_numreaders }
+    }
+
+    /// Size of the memory map, in bytes.
+    pub fn map_size(&self) -> size_t { self.map_size }
+
+    /// ID of the last used page.
+    pub fn last_page_number(&self) -> size_t { self.last_pgno }
+
+    /// ID of the last committed transaction.
+    pub fn last_txn_id(&self) -> size_t { self.last_txnid }
+
+    /// Maximum number of reader slots in the environment, as set by
+    /// `EnvironmentBuilder::set_max_readers`.
+    pub fn max_readers(&self) -> c_uint { self.max_readers }
+
+    /// Number of reader slots currently in use.
+    pub fn num_readers(&self) -> c_uint { self.num_readers }
+}
+
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 //// Environment Builder
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Options for opening or creating an environment.
-#[deriving(Show, PartialEq, Eq, Copy, Clone)]
+#[deriving(Show, PartialEq, Eq, Clone)]
 pub struct EnvironmentBuilder {
     flags: EnvironmentFlags,
     max_readers: Option<c_uint>,
     max_dbs: Option<c_uint>,
     map_size: Option<size_t>,
+    encryption_key: Option<Vec<u8>>,
+    autogrow_step: Option<size_t>,
 }
 
 impl EnvironmentBuilder {
@@ -162,6 +470,13 @@ impl EnvironmentBuilder {
                 lmdb_try_with_cleanup!(mdb_env_set_mapsize(env, map_size),
                                        mdb_env_close(env))
             }
+            if let Some(ref key) = self.encryption_key {
+                let key_val = MDB_val { mv_size: key.len() as size_t,
+                                        mv_data: key.as_ptr() as *mut c_void };
+                let mac_size = (ENCRYPTION_NONCE_LEN + ENCRYPTION_TAG_LEN) as size_t;
+                lmdb_try_with_cleanup!(mdb_env_set_encrypt(env, default_encdec, &key_val, mac_size),
+                                       mdb_env_close(env));
+            }
             lmdb_try_with_cleanup!(mdb_env_open(env,
                                                      path.to_c_str().as_ptr(),
                                                      self.flags.bits(),
@@ -169,7 +484,9 @@ impl EnvironmentBuilder {
                                    mdb_env_close(env));
         }
         Ok(Environment { env: env,
-                         dbi_open_mutex: Mutex::new(()) })
+                         dbi_open_mutex: Mutex::new(()),
+                         borrowed: false,
+                         autogrow_step: self.autogrow_step })
     }
 
     pub fn set_flags(&mut self, flags: EnvironmentFlags) -> &mut EnvironmentBuilder {
@@ -217,6 +534,112 @@ impl EnvironmentBuilder {
         self.map_size = Some(map_size);
         self
     }
+
+    /// Enables transparent encryption of database pages on disk.
+    ///
+    /// `key` is copied into the builder and is zeroed out when the builder is dropped. Every page
+    /// reserves `ENCRYPTION_NONCE_LEN + ENCRYPTION_TAG_LEN` (40) trailing bytes on disk for the
+    /// default cipher's per-page nonce and AEAD authentication tag; `open` configures LMDB with
+    /// that reservation itself, since it is entirely determined by the cipher `default_encdec`
+    /// implements and is not something a caller can vary without the callback also changing. This
+    /// requires LMDB to have been linked with encryption-at-rest support; `open` will return an
+    /// error if it was not.
+    pub fn set_encryption(&mut self, key: &[u8]) -> &mut EnvironmentBuilder {
+        self.encryption_key = Some(key.to_vec());
+        self
+    }
+
+    /// Enables automatic map growth on `MDB_MAP_FULL`.
+    ///
+    /// Normally a write that exceeds the environment's map size fails outright, and recovering
+    /// requires closing every handle to the environment before `set_map_size` can take effect.
+    /// With autogrow enabled, `Environment::with_write_txn` instead responds to `MDB_MAP_FULL` by
+    /// growing the map by `step` bytes (via `mdb_env_set_mapsize`) and retrying the transaction
+    /// exactly once.
+    pub fn set_autogrow(&mut self, step: size_t) -> &mut EnvironmentBuilder {
+        self.autogrow_step = Some(step);
+        self
+    }
+}
+
+impl Drop for EnvironmentBuilder {
+    fn drop(&mut self) {
+        if let Some(ref mut key) = self.encryption_key {
+            for byte in key.iter_mut() {
+                unsafe { ptr::write_volatile(byte, 0) };
+            }
+        }
+    }
+}
+
+/// Length, in bytes, of the random nonce `default_encdec` stores alongside each encrypted page.
+/// 24 bytes (the extended XChaCha20 nonce) makes nonce collisions across a database's pages
+/// practically impossible even when nonces are generated independently and at random, unlike the
+/// 12-byte nonce of plain ChaCha20Poly1305, where random generation alone is not enough headroom.
+const ENCRYPTION_NONCE_LEN: usize = 24;
+
+/// Length, in bytes, of the Poly1305 authentication tag `default_encdec` stores alongside each
+/// encrypted page.
+const ENCRYPTION_TAG_LEN: usize = 16;
+
+/// Default AEAD page encryption/decryption callback, registered with `mdb_env_set_encrypt` when
+/// `EnvironmentBuilder::set_encryption` has been used.
+///
+/// Encrypts with XChaCha20-Poly1305. Each page reserves `ENCRYPTION_NONCE_LEN +
+/// ENCRYPTION_TAG_LEN` trailing bytes -- the `mac_size` that `open` registers with
+/// `mdb_env_set_encrypt` -- to hold the authentication tag produced when that page was last
+/// written, followed by the freshly generated random nonce. Generating the nonce at random,
+/// rather than deriving it from anything about the page's contents, is what keeps it unique
+/// across every encryption of every page; the extended 192-bit nonce is what makes a random
+/// collision across that many encryptions negligible.
+extern "C" fn default_encdec(src: *const MDB_val,
+                              dst: *mut MDB_val,
+                              key: *const MDB_val,
+                              encdec: c_int) -> c_int {
+    unsafe {
+        let key_bytes = ::std::slice::from_raw_parts((*key).mv_data as *const u8,
+                                                       (*key).mv_size as usize);
+        let cipher = match XChaCha20Poly1305::new_from_slice(key_bytes) {
+            Ok(cipher) => cipher,
+            Err(_) => return -1,
+        };
+
+        let src_bytes = ::std::slice::from_raw_parts((*src).mv_data as *const u8,
+                                                       (*src).mv_size as usize);
+        let dst_bytes = ::std::slice::from_raw_parts_mut((*dst).mv_data as *mut u8,
+                                                          (*dst).mv_size as usize);
+        let reserved = ENCRYPTION_NONCE_LEN + ENCRYPTION_TAG_LEN;
+        if src_bytes.len() != dst_bytes.len() || src_bytes.len() < reserved {
+            return -1;
+        }
+        let body_len = src_bytes.len() - reserved;
+
+        if encdec == 1 {
+            let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+            match cipher.encrypt(&nonce, &src_bytes[..body_len]) {
+                Ok(sealed) => {
+                    // `sealed` is `body || tag`; the nonce itself isn't secret and is stored
+                    // alongside it so `decrypt` below can recover it.
+                    dst_bytes[..body_len + ENCRYPTION_TAG_LEN].copy_from_slice(&sealed);
+                    dst_bytes[body_len + ENCRYPTION_TAG_LEN..].copy_from_slice(nonce.as_slice());
+                    0
+                }
+                Err(_) => -1,
+            }
+        } else {
+            let nonce = XNonce::from_slice(&src_bytes[body_len + ENCRYPTION_TAG_LEN..]);
+            match cipher.decrypt(nonce, &src_bytes[..body_len + ENCRYPTION_TAG_LEN]) {
+                Ok(plaintext) => {
+                    dst_bytes[..body_len].copy_from_slice(&plaintext);
+                    for byte in dst_bytes[body_len..].iter_mut() {
+                        *byte = 0;
+                    }
+                    0
+                }
+                Err(_) => -1,
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -225,6 +648,7 @@ mod test {
     use std::io;
 
     use ffi::*;
+    use transaction::Transaction;
     use super::*;
 
     #[test]
@@ -271,6 +695,36 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_nested_txn() {
+        let dir = io::TempDir::new("test").unwrap();
+        let env = Environment::new().open(dir.path(), io::USER_RWX).unwrap();
+        let db = env.create_db(None, DatabaseFlags::empty()).unwrap();
+
+        let mut txn = env.begin_write_txn().unwrap();
+        {
+            // Aborting a nested transaction discards only the writes made within it; the parent
+            // never sees the key.
+            let mut nested = txn.begin_nested_txn().unwrap();
+            assert!(nested.put(db, b"aborted", b"value", WriteFlags::empty()).is_ok());
+            nested.abort();
+        }
+        assert!(txn.get(db, b"aborted").is_err());
+        {
+            // Committing a nested transaction merges its writes into the still-open parent
+            // instead of finishing the parent outright.
+            let mut nested = txn.begin_nested_txn().unwrap();
+            assert!(nested.put(db, b"committed", b"value", WriteFlags::empty()).is_ok());
+            assert!(nested.commit().is_ok());
+        }
+        assert_eq!(txn.get(db, b"committed").unwrap(), b"value");
+        assert!(txn.commit().is_ok());
+
+        // And the merged write survives the parent's own commit.
+        let txn2 = env.begin_read_txn().unwrap();
+        assert_eq!(txn2.get(db, b"committed").unwrap(), b"value");
+    }
+
     #[test]
     fn test_open_db() {
         let dir = io::TempDir::new("test").unwrap();
@@ -292,6 +746,35 @@ mod test {
         assert!(env.open_db(Some("testdb")).is_ok())
     }
 
+    #[test]
+    fn test_set_map_size_runtime() {
+        let dir = io::TempDir::new("test").unwrap();
+        let mut env = Environment::new().open(dir.path(), io::USER_RWX).unwrap();
+
+        let before = env.info().unwrap().map_size();
+        assert!(env.set_map_size_runtime(before + 1024 * 1024).is_ok());
+        assert!(env.info().unwrap().map_size() > before);
+    }
+
+    #[test]
+    fn test_autogrow() {
+        let dir = io::TempDir::new("test").unwrap();
+        let mut env = Environment::new().set_map_size(8192)
+                                        .set_autogrow(1024 * 1024)
+                                        .open(dir.path(), io::USER_RWX)
+                                        .unwrap();
+        let db = env.create_db(None, DatabaseFlags::empty()).unwrap();
+        let before = env.info().unwrap().map_size();
+
+        // A value far larger than the configured map size forces the first attempt to fail with
+        // MDB_MAP_FULL; `with_write_txn` should catch that, grow the map by the configured step,
+        // and retry once rather than surfacing the error to the caller.
+        let value = vec![0u8; 64 * 1024];
+        assert!(env.with_write_txn(|txn| txn.put(db, b"key", &value, WriteFlags::empty())).is_ok());
+
+        assert_eq!(env.info().unwrap().map_size(), before + 1024 * 1024);
+    }
+
     #[test]
     fn test_sync() {
         let dir = io::TempDir::new("test").unwrap();
@@ -305,4 +788,79 @@ mod test {
             assert!(env.sync(true).is_ok());
         }
     }
+
+    #[test]
+    fn test_stat_and_info() {
+        let dir = io::TempDir::new("test").unwrap();
+        let env = Environment::new().set_max_dbs(2)
+                                    .open(dir.path(), io::USER_RWX)
+                                    .unwrap();
+        let db = env.create_db(Some("testdb"), DatabaseFlags::empty()).unwrap();
+
+        let stat = env.stat().unwrap();
+        assert!(stat.page_size() > 0);
+
+        let info = env.info().unwrap();
+        assert!(info.map_size() > 0);
+        assert!(info.max_readers() > 0);
+
+        let txn = env.begin_read_txn().unwrap();
+        let db_stat = env.stat_db(&txn, db).unwrap();
+        assert_eq!(db_stat.entries(), 0);
+    }
+
+    #[test]
+    fn test_reader_list() {
+        let dir = io::TempDir::new("test").unwrap();
+        let env = Environment::new().open(dir.path(), io::USER_RWX).unwrap();
+
+        let txn = env.begin_read_txn().unwrap();
+        let readers = env.reader_list().unwrap();
+        assert!(!readers.is_empty());
+        drop(txn);
+
+        // Once the reader above has gone out of scope, there is nothing stale left to reclaim,
+        // but the scan itself should still succeed.
+        assert!(env.reader_check().is_ok());
+    }
+
+    #[test]
+    fn test_copy() {
+        let dir = io::TempDir::new("test").unwrap();
+        let env = Environment::new().open(dir.path(), io::USER_RWX).unwrap();
+
+        let copy_dir = io::TempDir::new("test-copy").unwrap();
+        assert!(env.copy(copy_dir.path()).is_ok());
+        assert!(Environment::new().open(copy_dir.path(), io::USER_RWX).is_ok());
+
+        let compact_dir = io::TempDir::new("test-compact").unwrap();
+        assert!(env.copy_with_options(compact_dir.path(), true).is_ok());
+        assert!(Environment::new().open(compact_dir.path(), io::USER_RWX).is_ok());
+    }
+
+    #[test]
+    fn test_raw_round_trip() {
+        let dir = io::TempDir::new("test").unwrap();
+        let env = Environment::new().open(dir.path(), io::USER_RWX).unwrap();
+
+        // `into_raw` relinquishes ownership without closing the handle, so `from_raw` can adopt
+        // it and close it exactly once when it's done.
+        let raw = env.into_raw();
+        let reopened = unsafe { Environment::from_raw(raw) };
+        assert!(reopened.sync(true).is_ok());
+    }
+
+    #[test]
+    fn test_borrow_raw_does_not_close() {
+        let dir = io::TempDir::new("test").unwrap();
+        let env = Environment::new().open(dir.path(), io::USER_RWX).unwrap();
+        let raw = env.env();
+
+        {
+            let borrowed = unsafe { Environment::borrow_raw(raw) };
+            assert!(borrowed.sync(true).is_ok());
+            // `borrowed` is dropped here; it must not close `raw` out from under `env`.
+        }
+        assert!(env.sync(true).is_ok());
+    }
 }
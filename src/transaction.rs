@@ -0,0 +1,208 @@
+use libc::{c_void, size_t};
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+use std::slice;
+
+use database::Database;
+use environment::Environment;
+use error::{LmdbResult, lmdb_result};
+use ffi::*;
+
+/// Wraps a byte slice in an `MDB_val` pointing at the slice's own storage.
+fn slice_to_val(slice: &[u8]) -> MDB_val {
+    MDB_val { mv_size: slice.len() as size_t, mv_data: slice.as_ptr() as *mut c_void }
+}
+
+/// Implemented by both read-only and read-write transactions.
+pub trait Transaction: Sized {
+
+    /// Returns a raw pointer to the underlying LMDB transaction.
+    fn txn(&self) -> *mut MDB_txn;
+
+    /// Commits the transaction.
+    fn commit(self) -> LmdbResult<()>;
+
+    /// Aborts the transaction.
+    fn abort(self);
+
+    /// Gets an item from a database.
+    ///
+    /// If the database supports duplicate keys (`MDB_DUPSORT`), the first data item for `key` is
+    /// returned; retrieving the other duplicates requires a cursor. The returned slice borrows
+    /// directly from the memory map and is valid for as long as the transaction is.
+    fn get<'txn>(&'txn self, database: Database, key: &[u8]) -> LmdbResult<&'txn [u8]> {
+        let mut key_val = slice_to_val(key);
+        let mut data_val = MDB_val { mv_size: 0, mv_data: ptr::null_mut() };
+        unsafe {
+            try!(lmdb_result(mdb_get(self.txn(), database.dbi(), &mut key_val, &mut data_val)));
+            Ok(slice::from_raw_parts(data_val.mv_data as *const u8, data_val.mv_size as usize))
+        }
+    }
+}
+
+/// Internal helpers shared by both transaction kinds.
+pub trait TransactionExt {
+    fn env(&self) -> *mut MDB_env;
+}
+
+impl<'env> TransactionExt for RoTransaction<'env> {
+    fn env(&self) -> *mut MDB_env {
+        unsafe { mdb_txn_env(self.txn) }
+    }
+}
+
+impl<'env> TransactionExt for RwTransaction<'env> {
+    fn env(&self) -> *mut MDB_env {
+        unsafe { mdb_txn_env(self.txn) }
+    }
+}
+
+/// A read-only transaction.
+///
+/// All database read operations require a transaction.
+pub struct RoTransaction<'env> {
+    txn: *mut MDB_txn,
+    _marker: PhantomData<&'env Environment>,
+}
+
+impl<'env> RoTransaction<'env> {
+
+    /// Begins a new read-only transaction on `env`.
+    pub fn new(env: &'env Environment) -> LmdbResult<RoTransaction<'env>> {
+        let mut txn: *mut MDB_txn = ptr::null_mut();
+        unsafe {
+            try!(lmdb_result(mdb_txn_begin(env.env(), ptr::null_mut(), MDB_RDONLY, &mut txn)));
+        }
+        Ok(RoTransaction { txn: txn, _marker: PhantomData })
+    }
+}
+
+impl<'env> Transaction for RoTransaction<'env> {
+
+    fn txn(&self) -> *mut MDB_txn {
+        self.txn
+    }
+
+    fn commit(self) -> LmdbResult<()> {
+        let result = unsafe { lmdb_result(mdb_txn_commit(self.txn)) };
+        mem::forget(self);
+        result
+    }
+
+    fn abort(self) {
+        unsafe { mdb_txn_abort(self.txn) }
+        mem::forget(self);
+    }
+}
+
+impl<'env> Drop for RoTransaction<'env> {
+    fn drop(&mut self) {
+        unsafe { mdb_txn_abort(self.txn) }
+    }
+}
+
+/// A read-write transaction.
+///
+/// All database write operations require a read-write transaction. Only one read-write
+/// transaction may be open on an environment at a time; `Environment::begin_write_txn` blocks
+/// until any prior one has committed or aborted.
+pub struct RwTransaction<'env> {
+    txn: *mut MDB_txn,
+    _marker: PhantomData<&'env Environment>,
+}
+
+impl<'env> RwTransaction<'env> {
+
+    /// Begins a new top-level read-write transaction on `env`.
+    pub fn new(env: &'env Environment) -> LmdbResult<RwTransaction<'env>> {
+        let mut txn: *mut MDB_txn = ptr::null_mut();
+        unsafe {
+            try!(lmdb_result(mdb_txn_begin(env.env(), ptr::null_mut(), 0, &mut txn)));
+        }
+        Ok(RwTransaction { txn: txn, _marker: PhantomData })
+    }
+
+    /// Begins a nested write transaction as a child of this one.
+    ///
+    /// While the child is open, the parent cannot be used for anything else; taking `&mut self`
+    /// and tying the child's lifetime to that borrow makes the borrow checker enforce this.
+    /// Committing the child merges its writes into the parent's dirty page set so they become
+    /// part of the parent's uncommitted transaction. Aborting the child, or simply dropping it,
+    /// discards only the writes made within it and leaves the parent's prior writes untouched.
+    /// LMDB has no concept of a nested *read-only* transaction, so there is no equivalent on
+    /// `RoTransaction`.
+    pub fn begin_nested_txn<'child>(&'child mut self) -> LmdbResult<RwTransaction<'child>> {
+        let mut child: *mut MDB_txn = ptr::null_mut();
+        unsafe {
+            try!(lmdb_result(mdb_txn_begin(mdb_txn_env(self.txn), self.txn, 0, &mut child)));
+        }
+        Ok(RwTransaction { txn: child, _marker: PhantomData })
+    }
+
+    /// Stores an item into a database.
+    ///
+    /// If the key already exists, the existing item is replaced unless `flags` contains
+    /// `MDB_NOOVERWRITE` (or, for `MDB_DUPSORT` databases, `MDB_NODUPDATA`), in which case the
+    /// call fails instead of overwriting.
+    pub fn put(&mut self,
+               database: Database,
+               key: &[u8],
+               data: &[u8],
+               flags: WriteFlags)
+               -> LmdbResult<()> {
+        let mut key_val = slice_to_val(key);
+        let mut data_val = slice_to_val(data);
+        unsafe {
+            lmdb_result(mdb_put(self.txn, database.dbi(), &mut key_val, &mut data_val, flags.bits()))
+        }
+    }
+
+    /// Deletes an item from a database.
+    ///
+    /// If the database supports duplicate keys (`MDB_DUPSORT`), passing `Some(data)` deletes only
+    /// the matching duplicate; passing `None` deletes all duplicates for `key`.
+    pub fn del(&mut self, database: Database, key: &[u8], data: Option<&[u8]>) -> LmdbResult<()> {
+        let mut key_val = slice_to_val(key);
+        unsafe {
+            match data {
+                Some(data) => {
+                    let mut data_val = slice_to_val(data);
+                    lmdb_result(mdb_del(self.txn, database.dbi(), &mut key_val, &mut data_val))
+                }
+                None => lmdb_result(mdb_del(self.txn, database.dbi(), &mut key_val, ptr::null_mut())),
+            }
+        }
+    }
+
+    /// Empties a database, deleting all of its key/value pairs.
+    pub fn clear_db(&mut self, database: Database) -> LmdbResult<()> {
+        unsafe {
+            lmdb_result(mdb_drop(self.txn, database.dbi(), 0))
+        }
+    }
+}
+
+impl<'env> Transaction for RwTransaction<'env> {
+
+    fn txn(&self) -> *mut MDB_txn {
+        self.txn
+    }
+
+    fn commit(self) -> LmdbResult<()> {
+        let result = unsafe { lmdb_result(mdb_txn_commit(self.txn)) };
+        mem::forget(self);
+        result
+    }
+
+    fn abort(self) {
+        unsafe { mdb_txn_abort(self.txn) }
+        mem::forget(self);
+    }
+}
+
+impl<'env> Drop for RwTransaction<'env> {
+    fn drop(&mut self) {
+        unsafe { mdb_txn_abort(self.txn) }
+    }
+}